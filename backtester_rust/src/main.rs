@@ -1,7 +1,9 @@
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 use rayon::prelude::*;
-use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rand_distr::{Distribution, Normal, StudentT};
 
 #[derive(Parser, Debug)]
 #[command(name = "backtester_rust")]
@@ -19,6 +21,65 @@ struct Args {
     iters: usize,
     #[arg(long)]
     out: String,
+    /// Global RNG seed; each strategy derives its own deterministic seed from
+    /// this value so reports are reproducible regardless of rayon ordering.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Synthetic return model: "normal", "student_t", or
+    /// "laplace"/"double_exponential".
+    #[arg(long, default_value = "normal")]
+    return_model: String,
+    /// Mean of the synthetic per-iteration return.
+    #[arg(long, default_value = "0.0")]
+    mean: f64,
+    /// Volatility (std) of the synthetic per-iteration return.
+    #[arg(long, default_value = "0.01")]
+    vol: f64,
+}
+
+// Draw one synthetic return from the selected heavy-/light-tailed model,
+// standardized to the requested mean and volatility.
+fn sample_return<R: rand::Rng>(model: &str, rng: &mut R, mean: f64, vol: f64) -> f64 {
+    match model {
+        "student_t" => {
+            // Standardized Student's t (df = 3) rescaled to the target vol, so
+            // the stream is fatter-tailed than a Normal at the same variance.
+            let df = 3.0_f64;
+            let t = StudentT::new(df).unwrap();
+            let z = t.sample(rng) / (df / (df - 2.0)).sqrt();
+            mean + vol * z
+        }
+        "laplace" | "double_exponential" => {
+            // Inverse-CDF draw from a Laplace with scale b = vol / sqrt(2) so
+            // its variance matches vol^2.
+            let b = vol / 2.0_f64.sqrt();
+            // Draw from the open interval (0, 1) via `Open01` and centre it, so
+            // `u` lands in (-0.5, 0.5) and `1 - 2|u|` is strictly positive — the
+            // log argument can never hit zero (which would give a -inf return).
+            let u: f64 = rng.sample::<f64, _>(rand::distributions::Open01) - 0.5;
+            mean - b * u.signum() * (1.0 - 2.0 * u.abs()).ln()
+        }
+        _ => {
+            let normal = Normal::new(mean, vol).unwrap_or_else(|_| Normal::new(0.0, 0.01).unwrap());
+            normal.sample(rng)
+        }
+    }
+}
+
+// Derive a strategy's deterministic seed from the global seed and a hash of its
+// name, so the result does not depend on the order rayon happens to run them.
+// Uses FNV-1a (a pinned, fully specified hash) rather than the std
+// `DefaultHasher`, whose SipHash output is explicitly not guaranteed stable
+// across Rust releases — a toolchain bump must not change a seeded report.
+fn strategy_seed(global_seed: u64, strategy: &str) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = FNV_OFFSET;
+    for byte in strategy.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    global_seed.wrapping_add(hash)
 }
 
 #[derive(Serialize, Deserialize)]
@@ -42,21 +103,25 @@ struct BacktestReport {
 
 fn run_strategy_backtest(
     strategy: &str,
-    symbols: &[String],
+    _symbols: &[String],
     iters: usize,
+    seed: u64,
+    return_model: &str,
+    mean: f64,
+    vol: f64,
 ) -> BacktestResult {
-    // Simplified backtest (would use actual historical data)
-    let mut returns: Vec<f64> = Vec::new();
-    
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    
+    // Simplified backtest (would use actual historical data). The synthetic
+    // return stream is drawn from a reproducibly seeded ChaCha20 generator so
+    // Sharpe/drawdown numbers repeat exactly across runs and machines.
+    let mut returns: Vec<f64> = Vec::with_capacity(iters);
+
+    let mut rng = ChaCha20Rng::seed_from_u64(strategy_seed(seed, strategy));
+
     for _ in 0..iters {
-        // Mock returns
-        let ret = (rng.gen::<f64>() - 0.5) * 0.02;
+        let ret = sample_return(return_model, &mut rng, mean, vol);
         returns.push(ret);
     }
-    
+
     let total_return = returns.iter().sum::<f64>();
     let mean_return = total_return / returns.len() as f64;
     let variance = returns.iter()
@@ -100,9 +165,22 @@ fn main() {
     println!("🚀 Starting backtest: {} strategies on {} symbols", strategies.len(), symbols.len());
     
     // Parallel backtesting
+    // Unseeded runs fall back to a fixed base seed of 0, so they repeat across
+    // runs as well; pass --seed to draw a different reproducible stream.
+    let seed = args.seed.unwrap_or(0);
     let results: Vec<BacktestResult> = strategies
         .par_iter()
-        .map(|strategy| run_strategy_backtest(strategy, &symbols, args.iters))
+        .map(|strategy| {
+            run_strategy_backtest(
+                strategy,
+                &symbols,
+                args.iters,
+                seed,
+                &args.return_model,
+                args.mean,
+                args.vol,
+            )
+        })
         .collect();
     
     let report = BacktestReport {
@@ -4,12 +4,15 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH, Instant};
 use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
 use std::path::PathBuf;
 
 // Risk state storage
 struct AppState {
     risk_state: Mutex<RiskState>,
     start_time: Instant,
+    health_cache: HealthCache,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -20,6 +23,10 @@ struct RiskState {
     cvar: f64,
     drawdown: f64,
     last_update: String,
+    // Latched once a liquidation begins; only cleared when the stricter
+    // liquidation-end health returns non-negative.
+    #[serde(default)]
+    being_liquidated: bool,
 }
 
 impl Default for RiskState {
@@ -30,6 +37,7 @@ impl Default for RiskState {
             var: 0.0,
             cvar: 0.0,
             drawdown: 0.0,
+            being_liquidated: false,
             last_update: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
@@ -40,7 +48,7 @@ impl Default for RiskState {
 }
 
 // Position data for risk evaluation
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct Position {
     symbol: String,
     quantity: f64,
@@ -53,6 +61,14 @@ struct RiskEvaluateRequest {
     portfolio_value: f64,
     #[serde(default = "default_confidence")]
     confidence: f64,
+    // Optional per-symbol volatilities; symbols absent here keep the 0.02
+    // default for backward compatibility.
+    #[serde(default)]
+    volatilities: Option<HashMap<String, f64>>,
+    // Optional symbol-by-symbol correlation matrix, aligned to `positions`
+    // order. Absent means the identity matrix (independent assets).
+    #[serde(default)]
+    correlations: Option<Vec<Vec<f64>>>,
 }
 
 fn default_confidence() -> f64 {
@@ -68,7 +84,7 @@ struct RiskEvaluateResponse {
     timestamp: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 struct TradeValidationRequest {
     symbol: String,
     side: String, // "buy" or "sell"
@@ -108,33 +124,104 @@ struct HealthResponse {
     uptime: String,
 }
 
-// Calculate Value at Risk (VaR) - Parametric method
-fn compute_var(positions: &[Position], portfolio_value: f64, confidence: f64) -> f64 {
-    if positions.is_empty() || portfolio_value <= 0.0 {
-        return 0.0;
-    }
-    
-    // Simplified parametric VaR: assume normal distribution
-    // VaR = -z_score * portfolio_volatility * portfolio_value
-    // Using historical volatility estimate (simplified)
-    
-    let total_value: f64 = positions.iter().map(|p| p.quantity * p.price).sum();
-    let exposure_ratio = total_value / portfolio_value;
-    
-    // Simplified: assume 2% daily volatility for crypto
-    let volatility = 0.02;
-    
-    // Z-score for confidence level
-    let z_score = match confidence {
+// Z-score for a given confidence level (one-sided normal quantile).
+fn z_score_for(confidence: f64) -> f64 {
+    match confidence {
         c if c >= 0.99 => 2.33,  // 99% confidence
         c if c >= 0.95 => 1.65,  // 95% confidence
         _ => 1.28,               // 90% confidence
+    }
+}
+
+// Calculate Value at Risk (VaR) - correlation-aware parametric method.
+//
+// Portfolio variance is `wᵀ Σ w`, where `w` is the vector of signed position
+// weights (`quantity × price / portfolio_value`) and
+// `Σ[i][j] = vol_i × vol_j × corr_ij`. VaR is `z_score × portfolio_vol ×
+// portfolio_value`. Missing vols default to 0.02 per symbol and a missing
+// correlation matrix defaults to the identity (independent assets).
+fn compute_var(
+    positions: &[Position],
+    portfolio_value: f64,
+    confidence: f64,
+    volatilities: Option<&HashMap<String, f64>>,
+    correlations: Option<&Vec<Vec<f64>>>,
+) -> Result<f64, String> {
+    if positions.is_empty() || portfolio_value <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let n = positions.len();
+
+    // Signed position weights.
+    let w: Vec<f64> = positions
+        .iter()
+        .map(|p| p.quantity * p.price / portfolio_value)
+        .collect();
+
+    // Per-symbol volatilities, defaulting to 2% daily for crypto.
+    let vol: Vec<f64> = positions
+        .iter()
+        .map(|p| {
+            volatilities
+                .and_then(|m| m.get(&p.symbol))
+                .copied()
+                .unwrap_or(0.02)
+        })
+        .collect();
+
+    // Correlation lookup: validated supplied matrix, or the identity.
+    let corr = match correlations {
+        Some(matrix) => {
+            if matrix.len() != n || matrix.iter().any(|row| row.len() != n) {
+                return Err(format!(
+                    "correlation matrix must be square {}×{}",
+                    n, n
+                ));
+            }
+            for i in 0..n {
+                for j in 0..n {
+                    if (matrix[i][j] - matrix[j][i]).abs() > 1e-9 {
+                        return Err("correlation matrix must be symmetric".to_string());
+                    }
+                }
+            }
+            Some(matrix)
+        }
+        None => None,
     };
-    
-    // VaR calculation
-    let var = z_score * volatility * portfolio_value * exposure_ratio;
-    
-    var
+
+    // portfolio variance = Σ_i Σ_j w_i w_j vol_i vol_j corr_ij
+    let mut variance = 0.0;
+    for i in 0..n {
+        for j in 0..n {
+            let corr_ij = match corr {
+                Some(m) => m[i][j].clamp(-1.0, 1.0),
+                None => {
+                    if i == j {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                }
+            };
+            variance += w[i] * w[j] * vol[i] * vol[j] * corr_ij;
+        }
+    }
+
+    // A correlation matrix must be positive-semidefinite for `wᵀΣw` to be a
+    // valid variance. A meaningfully negative quadratic form means the supplied
+    // matrix is not PSD, so reject it rather than silently flooring risk to
+    // zero; only a tiny negative attributable to rounding is floored. The
+    // tolerance scales with the diagonal (Σ w_i² vol_i²), which bounds the
+    // accumulated float error.
+    let scale: f64 = (0..n).map(|i| (w[i] * vol[i]).powi(2)).sum();
+    if variance < -1e-9 * scale.max(1.0) {
+        return Err("correlation matrix is not positive-semidefinite".to_string());
+    }
+    let portfolio_vol = variance.max(0.0).sqrt();
+
+    Ok(z_score_for(confidence) * portfolio_vol * portfolio_value)
 }
 
 // Calculate Conditional VaR (CVaR) - Expected loss beyond VaR
@@ -201,6 +288,459 @@ fn compute_drawdown_from_positions(positions: &[Position], portfolio_value: f64)
     }
 }
 
+// Which set of weights to apply. Maintenance weights are looser (an account
+// is liquidatable once maintenance health goes negative); the initial /
+// liquidation-end weights are stricter and gate when a liquidation can stop.
+#[derive(Clone, Copy, PartialEq)]
+enum HealthType {
+    Maintenance,
+    Initial,
+}
+
+// Per-symbol asset and liability weights for each health type. Everything
+// defaults to 1.0 so an unconfigured symbol is treated at face value.
+#[derive(Clone, Deserialize)]
+struct AssetWeights {
+    #[serde(default = "default_weight")]
+    maintenance_asset: f64,
+    #[serde(default = "default_weight")]
+    maintenance_liability: f64,
+    #[serde(default = "default_weight")]
+    initial_asset: f64,
+    #[serde(default = "default_weight")]
+    initial_liability: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl Default for AssetWeights {
+    fn default() -> Self {
+        AssetWeights {
+            maintenance_asset: 1.0,
+            maintenance_liability: 1.0,
+            initial_asset: 1.0,
+            initial_liability: 1.0,
+        }
+    }
+}
+
+// Margin weights loaded from config (env var `RISK_HEALTH_WEIGHTS`, a JSON map
+// of symbol -> weights), so operators can mark riskier assets down.
+struct HealthCache {
+    weights: HashMap<String, AssetWeights>,
+}
+
+impl HealthCache {
+    fn from_env() -> Self {
+        let weights = std::env::var("RISK_HEALTH_WEIGHTS")
+            .ok()
+            .and_then(|s| serde_json::from_str::<HashMap<String, AssetWeights>>(&s).ok())
+            .unwrap_or_default();
+        HealthCache { weights }
+    }
+
+    fn weights_for(&self, symbol: &str) -> AssetWeights {
+        self.weights.get(symbol).cloned().unwrap_or_default()
+    }
+
+    // health = Σ(asset_value × asset_weight) − Σ(liability_value × liability_weight).
+    // Positive-quantity positions are collateral; negative-quantity positions
+    // are borrows/liabilities.
+    fn compute_health(&self, positions: &[Position], health_type: HealthType) -> f64 {
+        let mut asset = 0.0;
+        let mut liability = 0.0;
+        for p in positions {
+            let w = self.weights_for(&p.symbol);
+            let value = (p.quantity * p.price).abs();
+            if p.quantity >= 0.0 {
+                let weight = match health_type {
+                    HealthType::Maintenance => w.maintenance_asset,
+                    HealthType::Initial => w.initial_asset,
+                };
+                asset += value * weight;
+            } else {
+                let weight = match health_type {
+                    HealthType::Maintenance => w.maintenance_liability,
+                    HealthType::Initial => w.initial_liability,
+                };
+                liability += value * weight;
+            }
+        }
+        asset - liability
+    }
+
+    // Single actionable signal: is this account underwater / must it be
+    // liquidated? Before liquidation starts, maintenance health going negative
+    // trips it; once latched, only the stricter liquidation-end health
+    // returning non-negative clears it.
+    fn is_liquidatable(&self, positions: &[Position], being_liquidated: bool) -> bool {
+        if being_liquidated {
+            self.compute_health(positions, HealthType::Initial) < 0.0
+        } else {
+            self.compute_health(positions, HealthType::Maintenance) < 0.0
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct HealthEvaluateRequest {
+    positions: Vec<Position>,
+}
+
+#[derive(Serialize)]
+struct MarginHealthResponse {
+    maintenance_health: f64,
+    liquidation_end_health: f64,
+    is_liquidatable: bool,
+    being_liquidated: bool,
+    timestamp: String,
+}
+
+// Margin-health / liquidation endpoint
+async fn margin_health(
+    req: web::Json<HealthEvaluateRequest>,
+    state: web::Data<AppState>,
+) -> ActixResult<HttpResponse> {
+    let positions = &req.positions;
+    let cache = &state.health_cache;
+
+    let maintenance_health = cache.compute_health(positions, HealthType::Maintenance);
+    let liquidation_end_health = cache.compute_health(positions, HealthType::Initial);
+
+    let mut risk_state = state.risk_state.lock().unwrap();
+    let is_liquidatable = cache.is_liquidatable(positions, risk_state.being_liquidated);
+
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    };
+
+    // Latch / clear the liquidation flag, recording each transition.
+    if !risk_state.being_liquidated {
+        if maintenance_health < 0.0 {
+            risk_state.being_liquidated = true;
+            append_risk_event(&RiskEvent {
+                timestamp: now(),
+                event_type: "liquidation_entered".to_string(),
+                metric: "maintenance_health".to_string(),
+                value: maintenance_health,
+                threshold: 0.0,
+                reason: format!(
+                    "Maintenance health {:.4} below zero; entering liquidation",
+                    maintenance_health
+                ),
+                request: serde_json::to_value(&*req).unwrap_or(serde_json::Value::Null),
+            });
+        }
+    } else if liquidation_end_health >= 0.0 {
+        risk_state.being_liquidated = false;
+        append_risk_event(&RiskEvent {
+            timestamp: now(),
+            event_type: "liquidation_cleared".to_string(),
+            metric: "liquidation_end_health".to_string(),
+            value: liquidation_end_health,
+            threshold: 0.0,
+            reason: format!(
+                "Initial health {:.4} restored; exiting liquidation",
+                liquidation_end_health
+            ),
+            request: serde_json::to_value(&*req).unwrap_or(serde_json::Value::Null),
+        });
+    }
+    persist_risk_state(&risk_state);
+
+    let response = MarginHealthResponse {
+        maintenance_health,
+        liquidation_end_health,
+        is_liquidatable,
+        being_liquidated: risk_state.being_liquidated,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string(),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SimulateSwapRequest {
+    positions: Vec<Position>,
+    portfolio_value: f64,
+    #[serde(default = "default_confidence")]
+    confidence: f64,
+    source_symbol: String,
+    target_symbol: String,
+    // Amount (in source units) to move out of the source position.
+    amount: f64,
+    // Execution price converting source units into deposited target value.
+    price: f64,
+}
+
+#[derive(Serialize)]
+struct SimulateSwapResponse {
+    approved: bool,
+    reason: String,
+    projected_exposure: f64,
+    projected_drawdown: f64,
+    projected_var: f64,
+    projected_cvar: f64,
+    projected_health: f64,
+    timestamp: String,
+}
+
+// Pre-trade "what-if swap": clone the positions, apply a hypothetical rebalance
+// and recompute every risk metric on the copy, without ever touching the
+// persisted `RiskState`. A net-borrow limit blocks swaps that would push the
+// source symbol short beyond the configured cap.
+async fn simulate_swap(
+    req: web::Json<SimulateSwapRequest>,
+    state: web::Data<AppState>,
+) -> ActixResult<HttpResponse> {
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    };
+
+    // Optional per-asset withdrawal fee and the net-borrow cap come from config.
+    let fees: HashMap<String, f64> = std::env::var("RISK_WITHDRAWAL_FEES")
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let borrow_limit: f64 = std::env::var("RISK_NET_BORROW_LIMIT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0.0);
+
+    let fee = fees.get(&req.source_symbol).copied().unwrap_or(0.0);
+
+    // Work on a copy of the positions so the live state is untouched.
+    let mut positions = req.positions.clone();
+
+    // Withdraw `amount` from the source, creating the position if absent.
+    let src_price = positions
+        .iter()
+        .find(|p| p.symbol == req.source_symbol)
+        .map(|p| p.price)
+        .unwrap_or(req.price);
+    match positions.iter_mut().find(|p| p.symbol == req.source_symbol) {
+        Some(p) => p.quantity -= req.amount,
+        None => positions.push(Position {
+            symbol: req.source_symbol.clone(),
+            quantity: -req.amount,
+            price: src_price,
+        }),
+    }
+
+    // Reject if the withdrawal pushes the source net-negative beyond the cap.
+    let new_src_qty = positions
+        .iter()
+        .find(|p| p.symbol == req.source_symbol)
+        .map(|p| p.quantity)
+        .unwrap_or(0.0);
+    if new_src_qty < 0.0 {
+        let borrow_value = (-new_src_qty) * src_price;
+        if borrow_value > borrow_limit {
+            let reason = format!(
+                "Swap would borrow {:.2} of {} (limit {:.2})",
+                borrow_value, req.source_symbol, borrow_limit
+            );
+            append_risk_event(&RiskEvent {
+                timestamp: now(),
+                event_type: "swap_rejected".to_string(),
+                metric: "net_borrow".to_string(),
+                value: borrow_value,
+                threshold: borrow_limit,
+                reason: reason.clone(),
+                request: serde_json::to_value(&*req).unwrap_or(serde_json::Value::Null),
+            });
+            return Ok(HttpResponse::Ok().json(SimulateSwapResponse {
+                approved: false,
+                reason,
+                projected_exposure: 0.0,
+                projected_drawdown: 0.0,
+                projected_var: 0.0,
+                projected_cvar: 0.0,
+                projected_health: 0.0,
+                timestamp: now(),
+            }));
+        }
+    }
+
+    // Deposit `amount × price` (net of the withdrawal fee) into the target.
+    let deposit_value = req.amount * (1.0 - fee) * req.price;
+    match positions.iter_mut().find(|p| p.symbol == req.target_symbol) {
+        Some(p) => {
+            if p.price > 0.0 {
+                p.quantity += deposit_value / p.price;
+            }
+        }
+        None => positions.push(Position {
+            symbol: req.target_symbol.clone(),
+            quantity: if req.price > 0.0 { deposit_value / req.price } else { 0.0 },
+            price: req.price,
+        }),
+    }
+
+    // Recompute every metric on the hypothetical portfolio.
+    let projected_exposure = compute_exposure(&positions, req.portfolio_value);
+    let projected_drawdown = compute_drawdown_from_positions(&positions, req.portfolio_value);
+    let projected_var =
+        compute_var(&positions, req.portfolio_value, req.confidence, None, None).unwrap_or(0.0);
+    let projected_cvar = compute_cvar(&positions, req.portfolio_value, projected_var, req.confidence);
+    let projected_health = state
+        .health_cache
+        .compute_health(&positions, HealthType::Maintenance);
+
+    Ok(HttpResponse::Ok().json(SimulateSwapResponse {
+        approved: true,
+        reason: "Within net-borrow limit".to_string(),
+        projected_exposure,
+        projected_drawdown,
+        projected_var,
+        projected_cvar,
+        projected_health,
+        timestamp: now(),
+    }))
+}
+
+#[derive(Deserialize, Debug)]
+struct HistoricalVarRequest {
+    // Either a ready-made portfolio return series...
+    #[serde(default)]
+    returns: Option<Vec<f64>>,
+    // ...or per-asset return series combined by `weights` (defaults to equal).
+    #[serde(default)]
+    asset_returns: Option<Vec<Vec<f64>>>,
+    #[serde(default)]
+    weights: Option<Vec<f64>>,
+    #[serde(default = "default_portfolio_value")]
+    portfolio_value: f64,
+    #[serde(default = "default_confidence")]
+    confidence: f64,
+}
+
+fn default_portfolio_value() -> f64 {
+    1.0
+}
+
+#[derive(Serialize)]
+struct HistoricalVarResponse {
+    historical_var: f64,
+    historical_cvar: f64,
+    scenarios: usize,
+    confidence: f64,
+    timestamp: String,
+}
+
+// Historical-simulation VaR and true tail-expectation CVaR, computed
+// non-parametrically from a supplied return series. No distributional
+// assumption: sort the outcomes, read the `(1 − confidence)` quantile as the
+// VaR loss (interpolating between order statistics), and average the whole
+// tail at or below it for CVaR.
+// Non-parametric VaR/CVaR from a return series. Returns the VaR quantile return
+// (interpolated between adjacent order statistics so the number moves smoothly
+// as the window grows) and the mean of every outcome at or below that quantile
+// (the true tail expectation). Both are raw returns; callers convert to losses.
+fn historical_var_cvar(returns: &[f64], confidence: f64) -> (f64, f64) {
+    let n = returns.len();
+
+    // Sort P&L outcomes ascending (worst losses first).
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let k = (1.0 - confidence) * n as f64;
+    let lower = k.floor() as usize;
+    let quantile_return = if lower + 1 < n {
+        let frac = k - lower as f64;
+        sorted[lower] + frac * (sorted[lower + 1] - sorted[lower])
+    } else {
+        sorted[lower.min(n - 1)]
+    };
+
+    let tail_end = (k.floor() as usize).min(n - 1);
+    let tail = &sorted[..=tail_end];
+    let mean_tail = tail.iter().sum::<f64>() / tail.len() as f64;
+
+    (quantile_return, mean_tail)
+}
+
+async fn historical_var(
+    req: web::Json<HistoricalVarRequest>,
+) -> ActixResult<HttpResponse> {
+    // Resolve the portfolio return series, combining per-asset series by weight
+    // when a pre-aggregated series is not supplied.
+    let returns: Vec<f64> = if let Some(r) = &req.returns {
+        r.clone()
+    } else if let Some(series) = &req.asset_returns {
+        if series.is_empty() {
+            Vec::new()
+        } else {
+            let n_assets = series.len();
+            let horizon = series.iter().map(|s| s.len()).min().unwrap_or(0);
+            let weights = req.weights.clone().unwrap_or_else(|| {
+                vec![1.0 / n_assets as f64; n_assets]
+            });
+            (0..horizon)
+                .map(|t| {
+                    series
+                        .iter()
+                        .zip(weights.iter())
+                        .map(|(s, w)| w * s[t])
+                        .sum()
+                })
+                .collect()
+        }
+    } else {
+        Vec::new()
+    };
+
+    let min_scenarios: usize = std::env::var("RISK_MIN_SCENARIOS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(20);
+
+    if returns.len() < min_scenarios {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!(
+                "insufficient data: {} observations, need at least {}",
+                returns.len(), min_scenarios
+            ),
+            "code": 400
+        })));
+    }
+
+    let n = returns.len();
+    let confidence = req.confidence.clamp(0.5, 0.999);
+
+    let (quantile_return, mean_tail) = historical_var_cvar(&returns, confidence);
+
+    // Report as positive monetary losses.
+    let historical_var = (-quantile_return).max(0.0) * req.portfolio_value;
+    let historical_cvar = (-mean_tail).max(0.0) * req.portfolio_value;
+
+    Ok(HttpResponse::Ok().json(HistoricalVarResponse {
+        historical_var,
+        historical_cvar,
+        scenarios: n,
+        confidence,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string(),
+    }))
+}
+
 // Risk evaluation endpoint
 async fn evaluate_risk(
     req: web::Json<RiskEvaluateRequest>,
@@ -215,7 +755,21 @@ async fn evaluate_risk(
     // Calculate metrics
     let exposure = compute_exposure(positions, portfolio_value);
     let drawdown = compute_drawdown_from_positions(positions, portfolio_value);
-    let var = compute_var(positions, portfolio_value, confidence);
+    let var = match compute_var(
+        positions,
+        portfolio_value,
+        confidence,
+        req.volatilities.as_ref(),
+        req.correlations.as_ref(),
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": e,
+                "code": 400
+            })));
+        }
+    };
     let cvar = compute_cvar(positions, portfolio_value, var, confidence);
     
     // Update state
@@ -264,15 +818,33 @@ async fn validate_trade(
     let trade = &req;
     let risk_state = state.risk_state.lock().unwrap();
     
+    let now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    };
+
     // Check 1: Current drawdown vs max drawdown
     if trade.current_drawdown > trade.max_drawdown {
+        let reason = format!(
+            "Current drawdown {:.2}% exceeds maximum {:.2}%",
+            trade.current_drawdown * 100.0,
+            trade.max_drawdown * 100.0
+        );
+        append_risk_event(&RiskEvent {
+            timestamp: now(),
+            event_type: "drawdown_breach".to_string(),
+            metric: "current_drawdown".to_string(),
+            value: trade.current_drawdown,
+            threshold: trade.max_drawdown,
+            reason: reason.clone(),
+            request: serde_json::to_value(&*req).unwrap_or(serde_json::Value::Null),
+        });
         return Ok(HttpResponse::Ok().json(TradeValidationResponse {
             approved: false,
-            reason: format!(
-                "Current drawdown {:.2}% exceeds maximum {:.2}%",
-                trade.current_drawdown * 100.0,
-                trade.max_drawdown * 100.0
-            ),
+            reason,
             post_trade_exposure: None,
             projected_drawdown: None,
             timestamp: SystemTime::now()
@@ -297,13 +869,23 @@ async fn validate_trade(
         .unwrap_or(0.75);
     
     if post_trade_exposure > max_exposure {
+        let reason = format!(
+            "Post-trade exposure {:.2}% exceeds maximum {:.2}%",
+            post_trade_exposure * 100.0,
+            max_exposure * 100.0
+        );
+        append_risk_event(&RiskEvent {
+            timestamp: now(),
+            event_type: "exposure_breach".to_string(),
+            metric: "post_trade_exposure".to_string(),
+            value: post_trade_exposure,
+            threshold: max_exposure,
+            reason: reason.clone(),
+            request: serde_json::to_value(&*req).unwrap_or(serde_json::Value::Null),
+        });
         return Ok(HttpResponse::Ok().json(TradeValidationResponse {
             approved: false,
-            reason: format!(
-                "Post-trade exposure {:.2}% exceeds maximum {:.2}%",
-                post_trade_exposure * 100.0,
-                max_exposure * 100.0
-            ),
+            reason,
             post_trade_exposure: Some(post_trade_exposure),
             projected_drawdown: None,
             timestamp: SystemTime::now()
@@ -354,6 +936,25 @@ async fn get_risk_state(state: web::Data<AppState>) -> ActixResult<HttpResponse>
     Ok(HttpResponse::Ok().json(risk_state.clone()))
 }
 
+#[derive(Deserialize, Debug)]
+struct RiskEventsQuery {
+    // How many of the most recent events to return, newest first.
+    #[serde(default = "default_event_limit")]
+    limit: usize,
+}
+
+fn default_event_limit() -> usize {
+    100
+}
+
+// Read recent entries from the append-only audit log, newest first, capped at
+// the `limit` query param (default 100). Lets operators replay *why* trades
+// were blocked and when risk limits were hit.
+async fn get_risk_events(query: web::Query<RiskEventsQuery>) -> ActixResult<HttpResponse> {
+    let events = read_risk_events(query.limit);
+    Ok(HttpResponse::Ok().json(events))
+}
+
 // Persist risk state to file
 fn persist_risk_state(state: &RiskState) {
     let state_path = std::env::var("RISK_STATE_PATH")
@@ -373,6 +974,68 @@ fn persist_risk_state(state: &RiskState) {
     }
 }
 
+// A single entry in the append-only risk-event audit log: why a trade was
+// blocked or when a risk limit / liquidation state changed, with the metric
+// that failed and the threshold it violated.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct RiskEvent {
+    timestamp: String,
+    event_type: String,
+    metric: String,
+    value: f64,
+    threshold: f64,
+    reason: String,
+    request: serde_json::Value,
+}
+
+// Append one event as a JSON line to the audit log (path via `RISK_EVENT_LOG`,
+// mirroring the `RISK_STATE_PATH` pattern). Never overwrites prior entries.
+fn append_risk_event(event: &RiskEvent) {
+    let log_path = std::env::var("RISK_EVENT_LOG")
+        .unwrap_or_else(|_| "state/risk_events.jsonl".to_string());
+
+    if let Some(parent) = PathBuf::from(&log_path).parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("⚠️ Failed to create event log directory: {}", e);
+            return;
+        }
+    }
+
+    let line = match serde_json::to_string(event) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("⚠️ Failed to serialize risk event: {}", e);
+            return;
+        }
+    };
+
+    match OpenOptions::new().create(true).append(true).open(&log_path) {
+        Ok(mut file) => {
+            if let Err(e) = writeln!(file, "{}", line) {
+                eprintln!("⚠️ Failed to append risk event: {}", e);
+            }
+        }
+        Err(e) => eprintln!("⚠️ Failed to open risk event log: {}", e),
+    }
+}
+
+// Read the most recent events back from the audit log.
+fn read_risk_events(limit: usize) -> Vec<RiskEvent> {
+    let log_path = std::env::var("RISK_EVENT_LOG")
+        .unwrap_or_else(|_| "state/risk_events.jsonl".to_string());
+
+    let data = match fs::read_to_string(&log_path) {
+        Ok(d) => d,
+        Err(_) => return Vec::new(),
+    };
+
+    data.lines()
+        .rev()
+        .take(limit)
+        .filter_map(|l| serde_json::from_str::<RiskEvent>(l).ok())
+        .collect()
+}
+
 // Load risk state from file
 fn load_risk_state() -> RiskState {
     let state_path = std::env::var("RISK_STATE_PATH")
@@ -529,6 +1192,7 @@ async fn main() -> std::io::Result<()> {
     let app_state = web::Data::new(AppState {
         risk_state: Mutex::new(initial_state),
         start_time: Instant::now(),
+        health_cache: HealthCache::from_env(),
     });
     
     HttpServer::new(move || {
@@ -542,6 +1206,10 @@ async fn main() -> std::io::Result<()> {
                     .route("/risk/validate", web::post().to(validate_trade))
                     .route("/risk/state", web::get().to(get_risk_state))
                     .route("/risk/stress", web::post().to(stress_test))
+                    .route("/risk/health", web::post().to(margin_health))
+                    .route("/risk/simulate-swap", web::post().to(simulate_swap))
+                    .route("/risk/var/historical", web::post().to(historical_var))
+                    .route("/risk/events", web::get().to(get_risk_events))
             )
             .default_service(web::route().to(|| async {
                 HttpResponse::NotFound().json(serde_json::json!({
@@ -554,3 +1222,51 @@ async fn main() -> std::io::Result<()> {
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A non-PSD but symmetric correlation matrix must be rejected, not floored
+    // to zero risk. Three assets pairwise correlated at -0.9 make `wᵀΣw`
+    // genuinely negative for an equal long book.
+    #[test]
+    fn non_psd_correlation_matrix_is_rejected() {
+        let positions = vec![
+            Position { symbol: "A".into(), quantity: 1.0, price: 100.0 },
+            Position { symbol: "B".into(), quantity: 1.0, price: 100.0 },
+            Position { symbol: "C".into(), quantity: 1.0, price: 100.0 },
+        ];
+        // Symmetric, unit diagonal, but not positive-semidefinite.
+        let corr = vec![
+            vec![1.0, -0.9, -0.9],
+            vec![-0.9, 1.0, -0.9],
+            vec![-0.9, -0.9, 1.0],
+        ];
+        let res = compute_var(&positions, 300.0, 0.99, None, Some(&corr));
+        assert!(res.is_err(), "expected non-PSD matrix to be rejected, got {:?}", res);
+    }
+
+    // The identity correlation (independent assets) stays a valid, positive VaR.
+    #[test]
+    fn identity_correlation_gives_positive_var() {
+        let positions = vec![
+            Position { symbol: "A".into(), quantity: 1.0, price: 100.0 },
+            Position { symbol: "B".into(), quantity: 1.0, price: 100.0 },
+        ];
+        let var = compute_var(&positions, 200.0, 0.99, None, None).unwrap();
+        assert!(var > 0.0);
+    }
+
+    // Historical CVaR is the mean of the worst tail, so it must be at least as
+    // severe as the VaR quantile itself (CVaR loss ≥ VaR loss).
+    #[test]
+    fn historical_cvar_dominates_var() {
+        let returns: Vec<f64> = (0..100).map(|i| -0.1 + i as f64 * 0.002).collect();
+        let (q, tail_mean) = historical_var_cvar(&returns, 0.95);
+        // Both are in the loss region (negative returns).
+        assert!(q < 0.0 && tail_mean < 0.0);
+        // The tail mean sits at or below the quantile.
+        assert!(tail_mean <= q + 1e-12);
+    }
+}
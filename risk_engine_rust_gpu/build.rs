@@ -0,0 +1,42 @@
+//! Resolve the linked `rand_chacha` version from `Cargo.lock` and expose it as
+//! the `RAND_CHACHA_VERSION` compile-time env var, so the health endpoint can
+//! report the generator version it was actually built against (audit trail)
+//! without a hand-maintained constant that drifts on a dependency bump.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    let version = locked_version("rand_chacha").unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=RAND_CHACHA_VERSION={}", version);
+}
+
+/// Walk up from the manifest directory to find `Cargo.lock` and read the
+/// `version` of the requested package out of its `[[package]]` table.
+fn locked_version(pkg: &str) -> Option<String> {
+    let mut dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").ok()?);
+    let lock = loop {
+        let candidate = dir.join("Cargo.lock");
+        if candidate.exists() {
+            break candidate;
+        }
+        if !dir.pop() {
+            return None;
+        }
+    };
+    println!("cargo:rerun-if-changed={}", lock.display());
+
+    let contents = fs::read_to_string(&lock).ok()?;
+    let needle = format!("name = \"{}\"", pkg);
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == needle {
+            // The `version = "..."` line follows the matching name entry.
+            let version_line = lines.next()?;
+            let raw = version_line.trim().strip_prefix("version = ")?;
+            return Some(raw.trim_matches('"').to_string());
+        }
+    }
+    None
+}
@@ -1,9 +1,19 @@
 use actix_web::{web, App, HttpServer, HttpResponse, Result as ActixResult};
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
-use rand::rngs::StdRng;
-use rand::SeedableRng;
-use rand_distr::{Distribution, Normal};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rand_distr::{Distribution, Normal, StandardNormal, StudentT, Pareto, Cauchy, Poisson};
+
+// Pinned counter-based generator used for every simulation. ChaCha20 has a
+// fixed, specified algorithm, so a `seed` reproduces byte-identical draws on
+// any machine and across `rand` releases — a hard requirement when a VaR
+// number may have to be re-derived in an audit.
+const RNG_ALGORITHM: &str = "ChaCha20";
+// Resolved from `Cargo.lock` at build time by `build.rs`, so the audited
+// generator version tracks the actually-linked `rand_chacha` instead of a
+// hand-maintained literal that silently drifts on a dependency bump.
+const RNG_CRATE_VERSION: &str = concat!("rand_chacha ", env!("RAND_CHACHA_VERSION"));
 
 #[derive(Deserialize, Debug)]
 struct MonteCarloVarRequest {
@@ -13,12 +23,65 @@ struct MonteCarloVarRequest {
     confidence: f64,
     #[serde(default)]
     seed: Option<u64>,
+    // Tail model to draw simulated returns from. One of
+    // "normal" | "student_t" | "cauchy" | "generalized_pareto".
+    #[serde(default = "default_distribution")]
+    distribution: String,
+    // Degrees of freedom for the Student's t model; fitted from the
+    // sample kurtosis when omitted.
+    #[serde(default)]
+    df: Option<f64>,
+    // Substream selector. Shards sharing a `seed` but carrying distinct
+    // `stream` ids each take a disjoint, reproducible slice of the same
+    // logical ChaCha20 sequence, so parallel draws never overlap.
+    #[serde(default)]
+    stream: Option<u64>,
+    // How to draw the simulated returns. "parametric" fits `distribution`
+    // to the sample; "bootstrap" resamples `returns` with replacement;
+    // "block_bootstrap" resamples contiguous blocks to keep autocorrelation.
+    #[serde(default = "default_method")]
+    method: String,
+    // Block length for the block bootstrap (ignored by the other methods).
+    #[serde(default)]
+    block_size: Option<usize>,
+    // Optional sampling weights aligned to `returns`. When present the engine
+    // resamples history with these weights (via the alias method) so risk
+    // teams can oversample specific regimes — crashes, rate shocks, etc.
+    #[serde(default)]
+    weights: Option<Vec<f64>>,
+    // Multi-step horizon: when set, the engine simulates full GBM price paths
+    // of this many steps (each of length `dt`) and uses the terminal
+    // log-return as the scenario, answering e.g. "10-day VaR".
+    #[serde(default)]
+    horizon: Option<usize>,
+    #[serde(default = "default_dt")]
+    dt: f64,
+    // Merton jump-diffusion overlay: jumps per unit time (`lambda`) and the
+    // size distribution of each jump in log-price (`jump_mean`, `jump_std`).
+    #[serde(default)]
+    lambda: Option<f64>,
+    #[serde(default)]
+    jump_mean: Option<f64>,
+    #[serde(default)]
+    jump_std: Option<f64>,
 }
 
 fn default_confidence() -> f64 {
     0.99
 }
 
+fn default_distribution() -> String {
+    "normal".to_string()
+}
+
+fn default_method() -> String {
+    "parametric".to_string()
+}
+
+fn default_dt() -> f64 {
+    1.0
+}
+
 #[derive(Serialize)]
 struct MonteCarloVarResponse {
     var: f64,
@@ -26,6 +89,90 @@ struct MonteCarloVarResponse {
     runtime_ms: f64,
     iterations: usize,
     confidence: f64,
+    // The requested `method` (parametric / bootstrap / block_bootstrap),
+    // echoed back as sent. A `horizon` or valid `weights` bypass it.
+    method: String,
+    // The requested tail model, echoed back as sent, plus any shape
+    // parameters fitted for it. It is not adjusted when `horizon`, `weights`
+    // or a non-parametric `method` bypass it, nor when an unknown name falls
+    // back to the Normal draw.
+    distribution: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    df: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tail_shape: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tail_threshold: Option<f64>,
+}
+
+// Fitted shape parameters, reported back alongside the VaR/CVaR so a caller
+// can tell which model and which fit produced a given risk number.
+#[derive(Default)]
+struct VarEstimate {
+    var: f64,
+    cvar: f64,
+    df: Option<f64>,
+    tail_shape: Option<f64>,
+    tail_threshold: Option<f64>,
+}
+
+// Vose's alias method: O(n) to build, O(1) per draw. Used for weighted
+// scenario sampling so a large return history can be resampled with arbitrary
+// regime weights without re-scanning the weights on every draw.
+struct AliasTable {
+    prob: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl AliasTable {
+    fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        // Scale the normalized weights by n so the average entry is 1.0.
+        let scaled: Vec<f64> = weights.iter().map(|w| w / total * n as f64).collect();
+
+        let mut prob = vec![0.0; n];
+        let mut alias = vec![0usize; n];
+        let mut small: Vec<usize> = Vec::new();
+        let mut large: Vec<usize> = Vec::new();
+        for (i, &p) in scaled.iter().enumerate() {
+            if p < 1.0 {
+                small.push(i);
+            } else {
+                large.push(i);
+            }
+        }
+
+        let mut scaled = scaled;
+        while let (Some(s), Some(l)) = (small.pop(), large.pop()) {
+            prob[s] = scaled[s];
+            alias[s] = l;
+            // Move the mass the small entry left unused back onto the large one
+            // and re-file it according to whether it is still "large".
+            scaled[l] = (scaled[l] + scaled[s]) - 1.0;
+            if scaled[l] < 1.0 {
+                small.push(l);
+            } else {
+                large.push(l);
+            }
+        }
+        // Anything left over sits exactly at probability 1.0.
+        for i in large.into_iter().chain(small) {
+            prob[i] = 1.0;
+            alias[i] = i;
+        }
+
+        AliasTable { prob, alias }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let i = rng.gen_range(0..self.prob.len());
+        if rng.gen_range(0.0..1.0) < self.prob[i] {
+            i
+        } else {
+            self.alias[i]
+        }
+    }
 }
 
 // Monte Carlo VaR simulation (CPU-based, parallelizable)
@@ -34,9 +181,20 @@ fn monte_carlo_var(
     iterations: usize,
     confidence: f64,
     seed: Option<u64>,
-) -> (f64, f64) {
+    distribution: &str,
+    df: Option<f64>,
+    stream: Option<u64>,
+    method: &str,
+    block_size: Option<usize>,
+    weights: Option<&[f64]>,
+    horizon: Option<usize>,
+    dt: f64,
+    lambda: Option<f64>,
+    jump_mean: Option<f64>,
+    jump_std: Option<f64>,
+) -> VarEstimate {
     if returns.is_empty() || iterations == 0 {
-        return (0.0, 0.0);
+        return VarEstimate::default();
     }
 
     // Calculate historical statistics
@@ -49,33 +207,235 @@ fn monte_carlo_var(
     let std_dev = variance.sqrt();
 
     if std_dev == 0.0 {
-        return (0.0, 0.0);
+        return VarEstimate::default();
     }
 
-    // Initialize RNG with seed if provided
-    let mut rng: StdRng = if let Some(s) = seed {
-        StdRng::seed_from_u64(s)
+    // Initialize the pinned ChaCha20 generator. A seeded run is fully
+    // reproducible; the optional stream id selects a disjoint substream so
+    // parallel shards keyed on the same seed never overlap their draws.
+    let mut rng: ChaCha20Rng = if let Some(s) = seed {
+        ChaCha20Rng::seed_from_u64(s)
     } else {
-        StdRng::from_entropy()
+        ChaCha20Rng::from_entropy()
     };
+    if let Some(stream_id) = stream {
+        rng.set_stream(stream_id);
+    }
 
-    // Normal distribution for sampling
-    let normal = Normal::new(mean, std_dev).unwrap_or_else(|_| Normal::new(0.0, 0.01).unwrap());
-
-    // Simulate portfolio returns
+    // Draw `iterations` simulated returns from the requested tail model.
+    // Normal is the historical default; the fat-tailed models track any
+    // shape parameters they fit so they can be reported back.
+    let mut fitted_df: Option<f64> = None;
+    let mut tail_shape: Option<f64> = None;
+    let mut tail_threshold: Option<f64> = None;
     let mut simulated_returns: Vec<f64> = Vec::with_capacity(iterations);
 
-    for _ in 0..iterations {
-        // Sample from distribution
-        let sample = normal.sample(&mut rng);
-        simulated_returns.push(sample);
+    // Multi-step horizon: simulate full GBM price paths and take the terminal
+    // log-return as the scenario, so horizon VaR (e.g. 10-day) can be read off
+    // the same percentile logic. A Merton jump component adds sudden crashes
+    // the diffusion term alone would miss.
+    if let Some(steps) = horizon.filter(|&h| h > 0) {
+        let drift = (mean - 0.5 * variance) * dt;
+        let diffusion = std_dev * dt.sqrt();
+        let jump_intensity = lambda.unwrap_or(0.0).max(0.0);
+        let jm = jump_mean.unwrap_or(0.0);
+        let js = jump_std.unwrap_or(0.0).max(0.0);
+        let poisson = if jump_intensity > 0.0 {
+            Poisson::new(jump_intensity * dt).ok()
+        } else {
+            None
+        };
+        let jump_dist = if js > 0.0 {
+            Normal::new(jm, js).ok()
+        } else {
+            None
+        };
+
+        for _ in 0..iterations {
+            let mut log_return = 0.0;
+            for _ in 0..steps {
+                let z: f64 = StandardNormal.sample(&mut rng);
+                log_return += drift + diffusion * z;
+                if let Some(p) = &poisson {
+                    let n_jumps = p.sample(&mut rng) as u64;
+                    for _ in 0..n_jumps {
+                        log_return += match &jump_dist {
+                            Some(d) => d.sample(&mut rng),
+                            None => jm,
+                        };
+                    }
+                }
+            }
+            simulated_returns.push(log_return);
+        }
+        let (var, cvar) = percentile_var(simulated_returns, confidence);
+        return VarEstimate { var, cvar, ..Default::default() };
+    }
+
+    // Weighted resampling takes precedence: when aligned weights are supplied
+    // we draw indices with the alias method so specific regimes are
+    // oversampled, then read VaR/CVaR off the resampled series as usual.
+    if let Some(weights) = weights {
+        if weights.len() == returns.len() && weights.iter().any(|&w| w > 0.0) {
+            let table = AliasTable::new(weights);
+            for _ in 0..iterations {
+                simulated_returns.push(returns[table.sample(&mut rng)]);
+            }
+            let (var, cvar) = percentile_var(simulated_returns, confidence);
+            return VarEstimate { var, cvar, ..Default::default() };
+        }
+    }
+
+    // Non-parametric methods resample the actual `returns` series instead of
+    // fitting a distribution to it, preserving its real (non-normal) shape.
+    match method {
+        "bootstrap" => {
+            // Draw each simulated return by sampling history with replacement.
+            for _ in 0..iterations {
+                let idx = rng.gen_range(0..returns.len());
+                simulated_returns.push(returns[idx]);
+            }
+            let (var, cvar) = percentile_var(simulated_returns, confidence);
+            return VarEstimate { var, cvar, ..Default::default() };
+        }
+        "block_bootstrap" => {
+            // Assemble the simulated series by concatenating randomly-chosen
+            // contiguous blocks of `block_size` (wrapping at the array end),
+            // emitting one historical observation per simulated observation so
+            // the real dispersion and volatility clustering inside each block
+            // are preserved rather than averaged away.
+            let block = block_size.unwrap_or(10).max(1).min(returns.len());
+            let mut start = 0usize;
+            for i in 0..iterations {
+                if i % block == 0 {
+                    start = rng.gen_range(0..returns.len());
+                }
+                let offset = i % block;
+                simulated_returns.push(returns[(start + offset) % returns.len()]);
+            }
+            let (var, cvar) = percentile_var(simulated_returns, confidence);
+            return VarEstimate { var, cvar, ..Default::default() };
+        }
+        _ => {}
+    }
+
+    match distribution {
+        "student_t" => {
+            // Fit degrees of freedom from the excess kurtosis of the sample
+            // (for a t-distribution, excess kurtosis = 6 / (df - 4)), unless
+            // the caller pinned `df` explicitly. Clamp to df > 4 so the
+            // variance used for rescaling stays finite.
+            let nu = df.unwrap_or_else(|| {
+                let m4 = returns.iter().map(|r| (r - mean).powi(4)).sum::<f64>()
+                    / returns.len() as f64;
+                let excess = m4 / variance.powi(2) - 3.0;
+                if excess > 0.0 {
+                    6.0 / excess + 4.0
+                } else {
+                    // Light tails: fall back to a near-Gaussian t.
+                    30.0
+                }
+            }).max(4.5);
+            fitted_df = Some(nu);
+
+            let t = StudentT::new(nu).unwrap_or_else(|_| StudentT::new(30.0).unwrap());
+            // A standard t has variance nu / (nu - 2); rescale the draw so the
+            // simulated series matches the sample mean and std.
+            let t_std = (nu / (nu - 2.0)).sqrt();
+            for _ in 0..iterations {
+                let z = t.sample(&mut rng) / t_std;
+                simulated_returns.push(mean + std_dev * z);
+            }
+        }
+        "cauchy" => {
+            // Heaviest tails: undefined mean/variance, so use the sample mean
+            // as location and std as scale. No shape parameters to report.
+            let cauchy = Cauchy::new(mean, std_dev)
+                .unwrap_or_else(|_| Cauchy::new(0.0, 0.01).unwrap());
+            for _ in 0..iterations {
+                simulated_returns.push(cauchy.sample(&mut rng));
+            }
+        }
+        "generalized_pareto" => {
+            // Peaks-over-threshold: fit a Pareto only to the lower-tail
+            // exceedances and stitch the body from the empirical distribution.
+            let mut body: Vec<f64> = returns.to_vec();
+            body.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            // Empirical 10th percentile as the tail threshold.
+            let thr_idx = ((0.10 * body.len() as f64) as usize).min(body.len() - 1);
+            let threshold = body[thr_idx];
+            tail_threshold = Some(threshold);
+
+            // Exceedances measured as positive losses below the threshold.
+            let exceedances: Vec<f64> = body
+                .iter()
+                .take_while(|&&r| r <= threshold)
+                .map(|&r| threshold - r)
+                .collect();
+            let tail_frac = exceedances.len() as f64 / body.len() as f64;
+
+            // Moment-matched shape estimate from the exceedance mean and the
+            // sample dispersion; larger shape => lighter tail.
+            let exc_mean = if exceedances.is_empty() {
+                std_dev
+            } else {
+                exceedances.iter().sum::<f64>() / exceedances.len() as f64
+            };
+            let shape = 1.0_f64.max(exc_mean / std_dev + 1.0);
+            tail_shape = Some(shape);
+
+            // A Pareto(scale, shape) has support `[scale, ∞)` and mean
+            // `scale·shape/(shape-1)`; subtracting `scale` yields an exceedance
+            // starting at 0 whose mean matches `exc_mean` (scale = exc_mean·(shape-1)).
+            let scale = (exc_mean * (shape - 1.0)).max(1e-9);
+            let pareto = Pareto::new(scale, shape)
+                .unwrap_or_else(|_| Pareto::new(0.01, 2.0).unwrap());
+            for _ in 0..iterations {
+                let u: f64 = rng.gen_range(0.0..1.0);
+                if u < tail_frac {
+                    // Draw a non-negative exceedance and place it below the threshold.
+                    let exceedance = pareto.sample(&mut rng) - scale;
+                    simulated_returns.push(threshold - exceedance);
+                } else {
+                    // Resample the empirical body above the threshold.
+                    let idx = rng.gen_range(thr_idx..body.len());
+                    simulated_returns.push(body[idx]);
+                }
+            }
+        }
+        _ => {
+            // Normal distribution for sampling (default)
+            let normal =
+                Normal::new(mean, std_dev).unwrap_or_else(|_| Normal::new(0.0, 0.01).unwrap());
+            for _ in 0..iterations {
+                simulated_returns.push(normal.sample(&mut rng));
+            }
+        }
+    }
+
+    let (var, cvar) = percentile_var(simulated_returns, confidence);
+
+    VarEstimate {
+        var,
+        cvar,
+        df: fitted_df,
+        tail_shape,
+        tail_threshold,
+    }
+}
+
+// Sort the simulated returns and read VaR/CVaR off the lower tail. Shared by
+// every sampling method so the percentile convention stays identical.
+fn percentile_var(mut simulated_returns: Vec<f64>, confidence: f64) -> (f64, f64) {
+    if simulated_returns.is_empty() {
+        return (0.0, 0.0);
     }
 
     // Sort to find percentile
     simulated_returns.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
     // Calculate VaR (loss at confidence level)
-    let var_index = ((1.0 - confidence) * iterations as f64) as usize;
+    let var_index = ((1.0 - confidence) * simulated_returns.len() as f64) as usize;
     let var = if var_index < simulated_returns.len() {
         -simulated_returns[var_index].min(0.0) // VaR is positive loss
     } else {
@@ -104,7 +464,12 @@ async fn health() -> ActixResult<HttpResponse> {
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "ok",
         "service": "NeoLight GPU Risk Engine (Monte Carlo)",
-        "version": "1.0.0"
+        "version": "1.0.0",
+        "rng": {
+            "algorithm": RNG_ALGORITHM,
+            "crate": RNG_CRATE_VERSION,
+            "reproducible": true
+        }
     })))
 }
 
@@ -126,15 +491,36 @@ async fn mc_var(
         })));
     }
 
-    let (var, cvar) = monte_carlo_var(returns, iterations, confidence, seed);
+    let estimate = monte_carlo_var(
+        returns,
+        iterations,
+        confidence,
+        seed,
+        &req.distribution,
+        req.df,
+        req.stream,
+        &req.method,
+        req.block_size,
+        req.weights.as_deref(),
+        req.horizon,
+        req.dt,
+        req.lambda,
+        req.jump_mean,
+        req.jump_std,
+    );
     let runtime_ms = start.elapsed().as_secs_f64() * 1000.0;
 
     let response = MonteCarloVarResponse {
-        var,
-        cvar,
+        var: estimate.var,
+        cvar: estimate.cvar,
         runtime_ms,
         iterations,
         confidence,
+        method: req.method.clone(),
+        distribution: req.distribution.clone(),
+        df: estimate.df,
+        tail_shape: estimate.tail_shape,
+        tail_threshold: estimate.tail_threshold,
     };
 
     Ok(HttpResponse::Ok().json(response))
@@ -159,3 +545,74 @@ async fn main() -> std::io::Result<()> {
     .await
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_distr::Pareto;
+
+    // Block bootstrap must emit historical observations verbatim (one per draw),
+    // not the mean of a block. With distinct inputs, every resulting VaR quantile
+    // is therefore one of the input magnitudes — a block-mean implementation
+    // would produce averaged values absent from the set.
+    #[test]
+    fn block_bootstrap_emits_per_observation_returns() {
+        let returns = [0.01, -0.02, 0.03, -0.04, 0.05];
+        let est = monte_carlo_var(
+            &returns, 5000, 0.90, Some(42), "normal", None, None,
+            "block_bootstrap", Some(3), None, None, 1.0, None, None, None,
+        );
+        // VaR is a positive loss equal to the negation of some historical value.
+        let matches = returns.iter().any(|&r| (r + est.var).abs() < 1e-12);
+        assert!(matches || est.var == 0.0, "var {} not a verbatim observation", est.var);
+    }
+
+    // Peaks-over-threshold exceedances start at zero above the threshold, so a
+    // tail draw `threshold - (pareto.sample() - scale)` is always ≤ threshold
+    // with no zero-probability gap below it.
+    #[test]
+    fn pot_tail_draws_stay_below_threshold() {
+        let threshold = -0.03_f64;
+        let exc_mean = 0.02_f64;
+        let std_dev = 0.02_f64;
+        let shape = 1.0_f64.max(exc_mean / std_dev + 1.0);
+        let scale = (exc_mean * (shape - 1.0)).max(1e-9);
+        let pareto = Pareto::new(scale, shape).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        for _ in 0..10_000 {
+            let exceedance = pareto.sample(&mut rng) - scale;
+            assert!(exceedance >= 0.0, "exceedance {} must be non-negative", exceedance);
+            let draw = threshold - exceedance;
+            assert!(draw <= threshold + 1e-12, "draw {} exceeded threshold", draw);
+        }
+    }
+
+    // Vose's alias method reproduces the supplied marginal weights.
+    #[test]
+    fn alias_method_matches_marginal_weights() {
+        let weights = vec![1.0, 3.0, 6.0];
+        let table = AliasTable::new(&weights);
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        let n = 200_000;
+        let mut counts = [0u32; 3];
+        for _ in 0..n {
+            counts[table.sample(&mut rng)] += 1;
+        }
+        let total: f64 = weights.iter().sum();
+        for (i, &wgt) in weights.iter().enumerate() {
+            let observed = counts[i] as f64 / n as f64;
+            let expected = wgt / total;
+            assert!((observed - expected).abs() < 0.01,
+                "index {}: observed {:.3} vs expected {:.3}", i, observed, expected);
+        }
+    }
+
+    // CVaR is the mean of the tail at or beyond the VaR point, so the CVaR loss
+    // is never smaller than the VaR loss.
+    #[test]
+    fn percentile_cvar_dominates_var() {
+        let sim: Vec<f64> = (0..1000).map(|i| -0.2 + i as f64 * 0.0004).collect();
+        let (var, cvar) = percentile_var(sim, 0.95);
+        assert!(cvar >= var - 1e-12, "cvar {} < var {}", cvar, var);
+    }
+}